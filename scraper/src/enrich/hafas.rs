@@ -0,0 +1,89 @@
+use chrono::NaiveDateTime;
+use hafas_rs::{DbProfile, HafasClient};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+use crate::Departure;
+
+/// One stop along a trip's full route, with planned/actual times for
+/// computing delay propagation (unlike the plain `ppth`/`cpth` name lists).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StopVisit {
+    pub station: String,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub planned_time: Option<String>,
+    pub actual_time: Option<String>,
+    pub delay_minutes: i32,
+}
+
+/// Look up each departure's HAFAS trip and attach its full stop sequence.
+/// A departure that can't be matched keeps an empty stop list.
+pub async fn enrich_with_hafas(
+    departures: &mut [Departure],
+    station_label: &str,
+) -> Result<(), Box<dyn Error>> {
+    let client = HafasClient::new(DbProfile::new());
+
+    for departure in departures.iter_mut() {
+        let stops = match fetch_stop_sequence(&client, departure, station_label).await {
+            Ok(stops) => stops,
+            Err(e) => {
+                eprintln!(
+                    "HAFAS enrichment failed for trip {} ({}): {}",
+                    departure.trip_id, departure.train, e
+                );
+                Vec::new()
+            }
+        };
+        departure.stop_sequence = serde_json::to_string(&stops)?;
+    }
+
+    Ok(())
+}
+
+async fn fetch_stop_sequence(
+    client: &HafasClient,
+    departure: &Departure,
+    station_label: &str,
+) -> Result<Vec<StopVisit>, Box<dyn Error>> {
+    let scheduled_time =
+        NaiveDateTime::parse_from_str(&departure.scheduled_time, "%Y-%m-%d %H:%M:%S")?;
+
+    let origin = client
+        .locations(station_label, 1)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or("origin station not found in HAFAS")?;
+    let destination = client
+        .locations(&departure.destination, 1)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or("destination not found in HAFAS")?;
+
+    let journey = client
+        .journeys(&origin, &destination, scheduled_time)
+        .await?
+        .into_iter()
+        .find(|j| j.legs().iter().any(|leg| leg.line_matches(&departure.train)))
+        .ok_or("no matching HAFAS journey")?;
+
+    Ok(journey
+        .legs()
+        .iter()
+        .find(|leg| leg.line_matches(&departure.train))
+        .map(|leg| leg.stopovers())
+        .unwrap_or_default()
+        .iter()
+        .map(|stopover| StopVisit {
+            station: stopover.stop.name.clone(),
+            lat: stopover.stop.latitude,
+            lon: stopover.stop.longitude,
+            planned_time: stopover.planned_arrival.clone(),
+            actual_time: stopover.actual_arrival.clone(),
+            delay_minutes: stopover.delay_minutes.unwrap_or(0),
+        })
+        .collect())
+}
@@ -0,0 +1,3 @@
+mod hafas;
+
+pub use hafas::{enrich_with_hafas, StopVisit};
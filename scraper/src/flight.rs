@@ -0,0 +1,246 @@
+use arrow::array::{BooleanArray, StringArray};
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo, HandshakeRequest,
+    HandshakeResponse, PutResult, Ticket,
+};
+use duckdb::Connection;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use tonic::{Request, Response, Status, Streaming};
+
+/// Ticket payload for `DoGet`: which station's departures to stream, and
+/// (optionally) only those observed since `since_timestamp` (unix seconds).
+#[derive(Debug, Deserialize, Serialize)]
+struct DepartureTicket {
+    station_id: String,
+    since_timestamp: Option<i64>,
+}
+
+/// Serves the latest ingested departures over Arrow Flight, so dashboards
+/// and notebooks can pull a zero-copy columnar stream instead of polling
+/// `../data/bronze/*.parquet`. Holds the current in-memory batch (written by
+/// the ingestion loop) plus a DuckDB handle for historical queries against
+/// `silver_departures`.
+pub struct DeparturesFlightService {
+    schema: Arc<Schema>,
+    current_batch: Arc<RwLock<Option<RecordBatch>>>,
+    duckdb_path: String,
+}
+
+impl DeparturesFlightService {
+    pub fn new(schema: Arc<Schema>, duckdb_path: String) -> Self {
+        Self {
+            schema,
+            current_batch: Arc::new(RwLock::new(None)),
+            duckdb_path,
+        }
+    }
+
+    /// Called by the ingestion loop after each fetch so `DoGet` can serve the
+    /// freshest batch without re-reading Parquet from disk.
+    pub fn publish(&self, batch: RecordBatch) {
+        *self.current_batch.write().unwrap() = Some(batch);
+    }
+
+    /// Fall back to `silver_departures` when there's no in-memory batch yet
+    /// (e.g. right after server startup, before the first fetch completes).
+    /// Filters on the real `station_id` column rather than guessing at a
+    /// `trip_id` prefix, since `trip_id` is an opaque per-event id from the
+    /// source feed, not a station-prefixed identifier.
+    fn query_silver(&self, station_id: &str, since_timestamp: i64) -> Result<RecordBatch, Status> {
+        let conn = Connection::open(&self.duckdb_path)
+            .map_err(|e| Status::internal(format!("failed to open duckdb: {}", e)))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT station_id, trip_id, train, destination, path, scheduled_time, platform, delay, disturbances \
+                 FROM silver_departures WHERE station_id = ? AND epoch(last_seen) >= ?",
+            )
+            .map_err(|e| Status::internal(format!("silver_departures query failed: {}", e)))?;
+
+        let rows = stmt
+            .query_map(duckdb::params![station_id, since_timestamp], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, i32>(7)?,
+                    row.get::<_, String>(8)?,
+                ))
+            })
+            .map_err(|e| Status::internal(format!("silver_departures query failed: {}", e)))?;
+
+        let mut departures = Vec::new();
+        for row in rows {
+            let (
+                station_id,
+                trip_id,
+                train,
+                destination,
+                path,
+                scheduled_time,
+                platform,
+                delay,
+                disturbances,
+            ) = row.map_err(|e| Status::internal(format!("silver_departures row error: {}", e)))?;
+            departures.push(crate::Departure {
+                station_id,
+                trip_id,
+                train,
+                destination,
+                path,
+                scheduled_time,
+                platform,
+                delay,
+                disturbances,
+                stop_sequence: String::new(),
+                stop_lat: None,
+                stop_lon: None,
+                route_short_name: String::new(),
+            });
+        }
+
+        crate::departures_to_batch(&departures)
+            .map_err(|e| Status::internal(format!("failed to build record batch: {}", e)))
+    }
+}
+
+/// Restrict `batch` to the rows for `station_id`. Used on the in-memory
+/// current-batch path, which (unlike `query_silver`) carries no observation
+/// timestamp to honor `since_timestamp` against, so only the station filter
+/// applies there.
+fn filter_by_station(batch: &RecordBatch, station_id: &str) -> Result<RecordBatch, Status> {
+    let station_column = batch
+        .column_by_name("station_id")
+        .ok_or_else(|| Status::internal("batch missing station_id column"))?
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .ok_or_else(|| Status::internal("station_id column has unexpected type"))?;
+
+    let mask = BooleanArray::from(
+        station_column
+            .iter()
+            .map(|v| v == Some(station_id))
+            .collect::<Vec<bool>>(),
+    );
+
+    filter_record_batch(batch, &mask).map_err(|e| Status::internal(format!("failed to filter batch: {}", e)))
+}
+
+#[tonic::async_trait]
+impl FlightService for DeparturesFlightService {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("handshake is not required"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let descriptor = FlightDescriptor::new_path(vec!["departures".to_string()]);
+        let info = FlightInfo::new()
+            .try_with_schema(&self.schema)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_descriptor(descriptor);
+
+        Ok(Response::new(futures::stream::iter(vec![Ok(info)]).boxed()))
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let info = FlightInfo::new()
+            .try_with_schema(&self.schema)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(info))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let ticket = request.into_inner();
+        let decoded: DepartureTicket = serde_json::from_slice(&ticket.ticket)
+            .map_err(|e| Status::invalid_argument(format!("bad ticket: {}", e)))?;
+
+        let batch = match self.current_batch.read().unwrap().clone() {
+            Some(batch) => filter_by_station(&batch, &decoded.station_id)?,
+            None => self.query_silver(
+                &decoded.station_id,
+                decoded.since_timestamp.unwrap_or(0),
+            )?,
+        };
+
+        let stream = FlightDataEncoderBuilder::new()
+            .with_schema(self.schema.clone())
+            .build(futures::stream::iter(vec![Ok(batch)]))
+            .map(|result| result.map_err(|e| Status::internal(e.to_string())));
+
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("this service is read-only"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("no custom actions"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(futures::stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("do_exchange is not supported"))
+    }
+}
+
+/// Run the Flight server on `addr`, alongside the ingestion loop in the same
+/// tokio runtime.
+pub async fn serve(
+    addr: std::net::SocketAddr,
+    service: Arc<DeparturesFlightService>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Arrow Flight server listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(FlightServiceServer::from_arc(service))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use gtfs_rt::{
+    trip_update::{stop_time_update::StopTimeEvent, StopTimeUpdate},
+    Alert, EntitySelector, FeedEntity, FeedHeader, FeedMessage, TimeRange, TripDescriptor,
+    TripUpdate,
+};
+use prost::Message;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write as _;
+
+use crate::Departure;
+
+/// Build a GTFS-Realtime `FeedMessage` carrying one `TripUpdate` entity per
+/// departure in the batch, plus one `Alert` entity per distinct disturbance
+/// category, so GTFS-RT consumers (OpenTripPlanner and friends) can read the
+/// same ingestion batch the Parquet bronze files are built from.
+pub fn build_feed(departures: &[Departure], feed_timestamp: u64) -> FeedMessage {
+    let header = FeedHeader {
+        gtfs_realtime_version: "2.0".to_string(),
+        incrementality: None,
+        timestamp: Some(feed_timestamp),
+    };
+
+    let mut entities: Vec<FeedEntity> = departures
+        .iter()
+        .map(|d| trip_update_entity(d, feed_timestamp))
+        .collect();
+
+    entities.extend(alert_entities(departures, feed_timestamp));
+
+    FeedMessage {
+        header,
+        entity: entities,
+    }
+}
+
+fn trip_update_entity(departure: &Departure, feed_timestamp: u64) -> FeedEntity {
+    let delay_seconds = departure.delay * 60;
+
+    let stop_time_update = StopTimeUpdate {
+        stop_sequence: None,
+        // trip_id is the XML's composite per-stop event id, not a real GTFS
+        // stops.txt id, so it doesn't belong in stop_id.
+        stop_id: None,
+        arrival: None,
+        departure: Some(StopTimeEvent {
+            delay: Some(delay_seconds),
+            time: scheduled_time_unix(departure).map(|t| t + i64::from(delay_seconds)),
+            uncertainty: None,
+        }),
+        departure_occupancy_status: None,
+        schedule_relationship: None,
+        platform_string: Some(departure.platform.clone()),
+    };
+
+    let trip_update = TripUpdate {
+        trip: TripDescriptor {
+            trip_id: Some(departure.trip_id.clone()),
+            route_id: None,
+            direction_id: None,
+            start_time: None,
+            start_date: None,
+            schedule_relationship: None,
+        },
+        vehicle: None,
+        stop_time_update: vec![stop_time_update],
+        timestamp: Some(feed_timestamp),
+        delay: Some(delay_seconds),
+        trip_properties: None,
+    };
+
+    FeedEntity {
+        id: format!("trip_update:{}", departure.trip_id),
+        is_deleted: None,
+        trip_update: Some(trip_update),
+        vehicle: None,
+        alert: None,
+        shape: None,
+    }
+}
+
+/// `departure.scheduled_time` is already formatted (`format_db_time`) as
+/// `%Y-%m-%d %H:%M:%S`; parse it back out as a unix timestamp. The caller adds
+/// the delay to it for `StopTimeEvent.time`, since GTFS-RT wants the predicted
+/// absolute time alongside the delay, not just the delay.
+fn scheduled_time_unix(departure: &Departure) -> Option<i64> {
+    NaiveDateTime::parse_from_str(&departure.scheduled_time, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|naive| Utc.from_utc_datetime(&naive).timestamp())
+}
+
+fn alert_entities(departures: &[Departure], feed_timestamp: u64) -> Vec<FeedEntity> {
+    let mut categories: Vec<&str> = departures
+        .iter()
+        .flat_map(|d| d.disturbances.split('|'))
+        .filter(|c| !c.is_empty())
+        .collect();
+    categories.sort();
+    categories.dedup();
+
+    categories
+        .into_iter()
+        .map(|category| {
+            let informed_entity: Vec<EntitySelector> = departures
+                .iter()
+                .filter(|d| d.disturbances.split('|').any(|c| c == category))
+                .map(|d| EntitySelector {
+                    agency_id: None,
+                    route_id: None,
+                    route_type: None,
+                    trip: Some(TripDescriptor {
+                        trip_id: Some(d.trip_id.clone()),
+                        route_id: None,
+                        direction_id: None,
+                        start_time: None,
+                        start_date: None,
+                        schedule_relationship: None,
+                    }),
+                    stop_id: None,
+                    direction_id: None,
+                })
+                .collect();
+
+            let alert = Alert {
+                active_period: vec![TimeRange {
+                    start: Some(feed_timestamp),
+                    end: None,
+                }],
+                informed_entity,
+                cause: None,
+                effect: None,
+                url: None,
+                header_text: None,
+                description_text: None,
+                tts_header_text: None,
+                tts_description_text: None,
+                severity_level: None,
+                image: None,
+                image_alternative_text: None,
+                cause_detail: None,
+                effect_detail: None,
+            };
+
+            FeedEntity {
+                id: format!("alert:{}", category),
+                is_deleted: None,
+                trip_update: None,
+                vehicle: None,
+                alert: Some(alert),
+                shape: None,
+            }
+        })
+        .collect()
+}
+
+/// Write the feed to `../data/gtfs_rt/trip_updates_<timestamp>.pb`, as the
+/// protobuf-encoded form of the same batch that is upserted into DuckDB (and
+/// written as a Parquet snapshot when `PARQUET_SNAPSHOT=1`).
+pub fn write_feed(departures: &[Departure], feed_timestamp: u64) -> Result<(), Box<dyn Error>> {
+    let feed = build_feed(departures, feed_timestamp);
+
+    std::fs::create_dir_all("../data/gtfs_rt")?;
+    let path = format!("../data/gtfs_rt/trip_updates_{}.pb", feed_timestamp);
+    let mut file = File::create(path)?;
+    file.write_all(&feed.encode_to_vec())?;
+
+    Ok(())
+}
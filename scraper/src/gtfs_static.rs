@@ -0,0 +1,116 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::Departure;
+
+#[derive(Debug, Deserialize)]
+struct StopRecord {
+    stop_id: String,
+    #[serde(default)]
+    stop_name: String,
+    stop_lat: f64,
+    stop_lon: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RouteRecord {
+    #[serde(default)]
+    route_short_name: String,
+}
+
+struct StopInfo {
+    name: String,
+    lat: f64,
+    lon: f64,
+}
+
+/// Reference data loaded from a static GTFS feed (`stops.txt`/`routes.txt`).
+/// Departure::trip_id isn't a GTFS trip_id, so we join on the EVA station_id
+/// and the train's category instead of trying to crosswalk via trips.txt.
+pub struct GtfsStatic {
+    stops_by_id: HashMap<String, StopInfo>,
+    route_short_names: HashMap<String, String>, // uppercased category -> canonical route_short_name
+}
+
+impl GtfsStatic {
+    /// Missing/unparsable files just leave that lookup empty.
+    pub fn load(dir: &str) -> Result<Self, Box<dyn Error>> {
+        let stops_by_id = Self::load_stops(dir).unwrap_or_else(|e| {
+            eprintln!("Could not load GTFS stops.txt from {}: {}", dir, e);
+            HashMap::new()
+        });
+
+        let route_short_names = Self::load_route_short_names(dir).unwrap_or_else(|e| {
+            eprintln!("Could not load GTFS routes.txt from {}: {}", dir, e);
+            HashMap::new()
+        });
+
+        Ok(Self {
+            stops_by_id,
+            route_short_names,
+        })
+    }
+
+    fn load_stops(dir: &str) -> Result<HashMap<String, StopInfo>, Box<dyn Error>> {
+        let mut map = HashMap::new();
+        let mut reader = csv::Reader::from_path(format!("{}/stops.txt", dir))?;
+        for record in reader.deserialize() {
+            let stop: StopRecord = record?;
+            map.insert(
+                stop.stop_id,
+                StopInfo {
+                    name: stop.stop_name,
+                    lat: stop.stop_lat,
+                    lon: stop.stop_lon,
+                },
+            );
+        }
+        Ok(map)
+    }
+
+    fn load_route_short_names(dir: &str) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let mut map = HashMap::new();
+        let mut reader = csv::Reader::from_path(format!("{}/routes.txt", dir))?;
+        for record in reader.deserialize() {
+            let route: RouteRecord = record?;
+            if !route.route_short_name.is_empty() {
+                map.insert(
+                    route.route_short_name.to_uppercase(),
+                    route.route_short_name,
+                );
+            }
+        }
+        Ok(map)
+    }
+
+    pub fn stop_coords(&self, stop_id: &str) -> Option<(f64, f64)> {
+        self.stops_by_id.get(stop_id).map(|s| (s.lat, s.lon))
+    }
+
+    pub fn stop_name(&self, stop_id: &str) -> Option<&str> {
+        self.stops_by_id.get(stop_id).map(|s| s.name.as_str())
+    }
+
+    fn route_short_name(&self, category: &str) -> Option<&str> {
+        self.route_short_names
+            .get(&category.to_uppercase())
+            .map(|s| s.as_str())
+    }
+}
+
+pub fn join_departures(gtfs: &GtfsStatic, station_id: &str, departures: &mut [Departure]) {
+    let station_coords = gtfs.stop_coords(station_id);
+
+    for departure in departures.iter_mut() {
+        if let Some((lat, lon)) = station_coords {
+            departure.stop_lat = Some(lat);
+            departure.stop_lon = Some(lon);
+        }
+
+        let category = departure.train.split_whitespace().next().unwrap_or("");
+        if let Some(short_name) = gtfs.route_short_name(category) {
+            departure.route_short_name = short_name.to_string();
+        }
+    }
+}
@@ -1,99 +1,41 @@
 use chrono::{Utc, NaiveDateTime};
-use quick_xml::de::from_str;
-use reqwest::Client;
-use serde::Deserialize;
-use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
-use arrow::array::{StringArray, Int32Array};
+use arrow::array::{Float64Array, StringArray, Int32Array};
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
 use parquet::arrow::arrow_writer::ArrowWriter;
 
+use std::collections::HashMap;
 use std::env;
 use duckdb::Connection;
 
+mod enrich;
+mod flight;
+mod gtfs_rt;
+mod gtfs_static;
+mod sink;
+mod sources;
 
-#[derive(Debug)]
-
-
-struct Departure {
-    trip_id: String,
-    train: String,
-    destination: String,
-    path: String,
-    scheduled_time: String,
-    platform: String, // NEW
-    delay: i32,
-    disturbances: String, 
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct Timetable {
-    #[serde(rename = "s", default)]
-    stops: Vec<Stop>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-
-struct Stop {
-    #[serde(rename = "@id")] 
-    id: String,
-    #[serde(rename = "m", default)] 
-    messages: Vec<Message>, 
-    #[serde(rename = "ar")]
-    arrivals: Option<TrainEvent>,
-    #[serde(rename = "dp")]
-    departures: Option<TrainEvent>,
-    #[serde(rename = "tl")]
-    train_line: Option<TrainLine>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct Message {
-    #[serde(rename = "@t")] 
-    msg_type: Option<String>,      
-    #[serde(rename = "@cat")] 
-    category: Option<String>,     
-    #[serde(rename = "@from")] 
-    valid_from: Option<String>,
-    #[serde(rename = "@to")] 
-    valid_to: Option<String>,
-    #[serde(rename = "@ts")] 
-    timestamp: Option<String>,    
-    #[serde(rename = "@ts-tts")] 
-    ts_tts: Option<String>,       
-}
-
-
-
-#[derive(Debug, Deserialize, Clone)]
-struct TrainEvent {
-    #[serde(rename = "@ct")]  actual_time: Option<String>,
-    #[serde(rename = "@pt")]  planned_time: Option<String>,
-    #[serde(rename = "@ppth")] planned_path: Option<String>,
-    #[serde(rename = "@cpth")] changed_path: Option<String>,
-    #[serde(rename = "@pp")]   platform: Option<String>,
-    #[serde(rename = "@l")]    line: Option<String>,
-    #[serde(rename = "@c")]    category: Option<String>,
-    #[serde(rename = "@n")]    number: Option<String>,
-    #[serde(rename = "m", default)] messages: Vec<Message>,
-}
+use sources::{choose_source, TimetableSource};
 
-impl TrainEvent {
-    fn path(&self) -> Option<String> {
-        self.changed_path.clone().or(self.planned_path.clone())
-    }
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct TrainLine {
-    #[serde(rename = "@c")] // Category (ICE)
-    category: Option<String>,
-    #[serde(rename = "@n")] // Number (147)
-    number: Option<String>,
+#[derive(Debug)]
+pub(crate) struct Departure {
+    pub(crate) station_id: String, // the EVA station id this departure was fetched for
+    pub(crate) trip_id: String,
+    pub(crate) train: String,
+    pub(crate) destination: String,
+    pub(crate) path: String,
+    pub(crate) scheduled_time: String,
+    pub(crate) platform: String, // NEW
+    pub(crate) delay: i32,
+    pub(crate) disturbances: String,
+    pub(crate) stop_sequence: String, // JSON-encoded Vec<enrich::StopVisit>, empty when not enriched
+    pub(crate) stop_lat: Option<f64>,        // from the GTFS stops.txt sidecar
+    pub(crate) stop_lon: Option<f64>,        // from the GTFS stops.txt sidecar
+    pub(crate) route_short_name: String, // from the GTFS routes.txt sidecar, "" when unresolved
 }
 
 #[tokio::main]
@@ -101,6 +43,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let limit = 500;
 
     let conn = Connection::open("../data/dbt.duckdb")?;
+    sink::ensure_schema(&conn)?;
     let count: i32 = conn.query_row(
         "SELECT count(*) FROM silver_departures", 
         [], 
@@ -116,23 +59,51 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 
 
-    let client = Client::new();
-    let station_id = "8011160"; // Berlin Hbf
+    let station_id = env::var("STATION_ID").unwrap_or_else(|_| "8011160".to_string()); // Berlin Hbf by default
+    let source_name = env::var("TIMETABLE_SOURCE").unwrap_or_else(|_| "db".to_string());
     let client_id = env::var("DB_CLIENT_ID")
         .expect("DB_CLIENT_ID not set in GitHub Secrets");
     let api_key = env::var("DB_API_KEY")
         .expect("DB_API_KEY not set in GitHub Secrets");
 
+    let source = choose_source(&source_name, client_id, api_key);
+
+    let gtfs_dir = env::var("GTFS_STATIC_DIR").unwrap_or_else(|_| "../data/gtfs".to_string());
+    let gtfs_static = gtfs_static::GtfsStatic::load(&gtfs_dir)?;
+    let station_label = gtfs_static
+        .stop_name(&station_id)
+        .unwrap_or(station_id.as_str())
+        .to_string();
+
+    let flight_service = Arc::new(flight::DeparturesFlightService::new(
+        departures_schema(),
+        "../data/dbt.duckdb".to_string(),
+    ));
+    let flight_addr: std::net::SocketAddr = env::var("FLIGHT_ADDR")
+        .unwrap_or_else(|_| "0.0.0.0:50051".to_string())
+        .parse()?;
+    let flight_handle = {
+        let flight_service = flight_service.clone();
+        tokio::spawn(async move {
+            if let Err(e) = flight::serve(flight_addr, flight_service).await {
+                eprintln!("Arrow Flight server exited: {}", e);
+            }
+        })
+    };
+
     // 2. Ensure the log and data directories exist
     std::fs::create_dir_all("logs")?;
     std::fs::create_dir_all("../data/bronze")?; 
     let heartbeat_file = "logs/heartbeat.log";
 
-    println!("Starting Enriched DB Ingestion Service for: {}", station_id);
+    println!(
+        "Starting Enriched Ingestion Service ({}) for: {} ({})",
+        source_name, station_label, station_id
+    );
 
     println!("Building Plan Lookup Map...");
-    
-    let mut plan_map = fetch_plan_map(&client, station_id, &client_id, &api_key).await?;
+
+    let plan_map = build_plan_map(source.as_ref(), &station_id).await?;
     // let mut last_refresh_hour = Utc::now().hour();
     println!("Lookup Map ready with {} train definitions.", plan_map.len());
 
@@ -146,21 +117,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         
     //     if now.hour() != last_refresh_hour {
     //         println!("Hour changed! Refreshing Plan Lookup Map...");
-    //         if let Ok(new_map) = fetch_plan_map(&client, station_id, client_id, api_key).await {
+    //         if let Ok(new_map) = build_plan_map(source.as_ref(), station_id).await {
     //             plan_map = new_map;
     //             last_refresh_hour = now.hour();
     //         }
     //     }
 
-        match fetch_departures(&client, station_id, &client_id, &api_key, &plan_map).await {
-            Ok(departures) => {
+        match source.fetch_changes(&station_id, &plan_map).await {
+            Ok(mut departures) => {
                 println!("Fetched {} departures", departures.len());
 
+                gtfs_static::join_departures(&gtfs_static, &station_id, &mut departures);
+
+                if env::var("HAFAS_ENRICHMENT").as_deref() == Ok("1") {
+                    if let Err(e) = enrich::enrich_with_hafas(&mut departures, &station_label).await {
+                        eprintln!("HAFAS enrichment failed: {}", e);
+                    }
+                }
+
                 if !departures.is_empty() {
-                    if let Err(e) = write_parquet(&departures) {
-                        eprintln!("Failed to write Parquet: {}", e);
+                    if let Err(e) = sink::upsert_departures(&conn, &departures) {
+                        eprintln!("Failed to upsert departures into DuckDB: {}", e);
+                    } else {
+                        println!("Upserted {} departures into silver_departures.", departures.len());
+                    }
+
+                    if env::var("PARQUET_SNAPSHOT").as_deref() == Ok("1") {
+                        if let Err(e) = write_parquet(&departures) {
+                            eprintln!("Failed to write Parquet snapshot: {}", e);
+                        } else {
+                            println!("Parquet snapshot written.");
+                        }
+                    }
+
+                    match departures_to_batch(&departures) {
+                        Ok(batch) => flight_service.publish(batch),
+                        Err(e) => eprintln!("Failed to publish batch to Flight service: {}", e),
+                    }
+
+                    let feed_timestamp = Utc::now().timestamp() as u64;
+                    if let Err(e) = gtfs_rt::write_feed(&departures, feed_timestamp) {
+                        eprintln!("Failed to write GTFS-Realtime feed: {}", e);
                     } else {
-                        println!("Enriched Parquet file written.");
+                        println!("GTFS-Realtime TripUpdate feed written.");
                     }
                 }
 
@@ -176,195 +175,69 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         // sleep(Duration::from_secs(60)).await;
     // }
+
+    // The burst-ingestion fetch above is one-shot, but the Flight server it
+    // just published a batch to needs to keep listening so dashboards and
+    // notebooks can actually reach it — awaiting the handle here is what
+    // keeps the process (and its socket) alive instead of tokio dropping
+    // the spawned task the moment `main` returns.
+    println!("Ingestion burst complete; keeping the Arrow Flight service running on {}.", flight_addr);
+    flight_handle.await?;
     Ok(())
 }
 
-async fn fetch_plan_map(
-    client: &Client,
+async fn build_plan_map(
+    source: &dyn TimetableSource,
     station_id: &str,
-    client_id: &str,
-    api_key: &str,
 ) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
     let mut map = HashMap::new();
     let now = Utc::now();
-    
+
     for i in -2..6 {
         let offset = chrono::Duration::try_hours(i).unwrap_or_else(|| chrono::Duration::zero());
         let target_time = now + offset;
-        
+
         let date = target_time.format("%y%m%d").to_string();
         let hour = target_time.format("%H").to_string();
-        
-        println!("Fetching plan for {} hour: {}", if i < 0 { "past" } else { "future/current" }, hour);
-
-   
-        let url = format!(
-    "https://apis.deutschebahn.com/db-api-marketplace/apis/timetables/v1/plan/{}/{}/{}?sub=yes", // Added ?sub=yes
-    station_id, date, hour
-);
-
-        let resp = client.get(&url)
-            .header("DB-Client-Id", client_id)
-            .header("DB-Api-Key", api_key)
-            .header("Accept", "application/xml")
-            .send().await?;
-
-        if resp.status().is_success() {
-            let text = resp.text().await?;
-            let timetable: Timetable = from_str(&text)?;
-            
-
-            
-
-            for stop in timetable.stops {
-                    let mut train_name = None;
-
-                    let event = stop.departures.as_ref().or(stop.arrivals.as_ref());
-                    if let Some(ev) = event {
-                        if let Some(l) = &ev.line {
-                            if !l.is_empty() {
-                                train_name = Some(l.clone());
-                            }
-                        }
-                    }
 
-                    if train_name.is_none() {
-                        if let Some(tl) = &stop.train_line {
-                            match (&tl.category, &tl.number) {
-                                (Some(c), Some(n)) => train_name = Some(format!("{} {}", c, n)),
-                                (Some(c), None) => train_name = Some(c.clone()),
-                                _ => {}
-                            }
-                        }
-                    }
-
-                    let final_name = train_name.unwrap_or_else(|| {
-                        format!("ID:{}", &stop.id.chars().take(8).collect::<String>())
-                    });
+        println!("Fetching plan for {} hour: {}", if i < 0 { "past" } else { "future/current" }, hour);
 
-                    map.insert(stop.id, final_name);
-                }
-        } else {
-            eprintln!(" Could not fetch hour {}: Status {}", hour, resp.status());
+        match source.fetch_plan(station_id, &date, &hour).await {
+            Ok(hour_map) => map.extend(hour_map),
+            Err(e) => eprintln!(" Could not fetch hour {}: {}", hour, e),
         }
-        
-     
+
         sleep(Duration::from_millis(500)).await;
     }
     Ok(map)
 }
 
 
-async fn fetch_departures(
-    client: &Client,
-    station_id: &str,
-    client_id: &str,
-    api_key: &str,
-    plan_map: &HashMap<String, String>,
-) -> Result<Vec<Departure>, Box<dyn std::error::Error>> {
-    let url = format!(
-        "https://apis.deutschebahn.com/db-api-marketplace/apis/timetables/v1/fchg/{}?sub=yes",
-        station_id
-    );
 
-    let resp = client.get(&url)
-        .header("DB-Client-Id", client_id)
-        .header("DB-Api-Key", api_key)
-        .header("Accept", "application/xml")
-        .send().await?;
-
-    let text = resp.text().await?;
-    let timetable: Timetable = from_str(&text)?;
-    let mut flat_departures = vec![];
-
-    for stop in timetable.stops {
-        if let Some(dp) = &stop.departures {
-            
-            let raw_time = dp.actual_time.clone().unwrap_or_else(|| {
-                dp.planned_time.clone().unwrap_or_default()
-            });
-            let formatted_time = format_db_time(&raw_time);
-
-            let delay_min = if let (Some(p), Some(a)) = (&dp.planned_time, &dp.actual_time) {
-                calculate_delay_minutes(p, a)
-            } else {
-                0
-            };
-
-            
-            let train_name = {
-                let change_name = match (&dp.line, &dp.category, &dp.number) {
-                    (Some(l), _, _) if !l.is_empty() => Some(l.clone()),
-                    (_, Some(c), Some(n)) => Some(format!("{} {}", c, n)),
-                    _ => None,
-                };
-
-                change_name
-                    .or_else(|| plan_map.get(&stop.id).cloned())
-                    .or_else(|| {
-                        stop.train_line.as_ref().and_then(|tl| {
-                            match (&tl.category, &tl.number) {
-                                (Some(c), Some(n)) => Some(format!("{} {}", c, n)),
-                                (Some(c), None) => Some(c.clone()),
-                                _ => None,
-                            }
-                        })
-                    })
-                    .unwrap_or_else(|| "Unknown".to_string())
-            };
-
-            
-            let arrival_path = stop.arrivals.as_ref()
-                .and_then(|ar| ar.path())
-                .unwrap_or_default();
-
-            let departure_path = dp.path().unwrap_or_default(); 
-
-            let full_route = if arrival_path.is_empty() && departure_path.is_empty() {
-                "Berlin Hbf".to_string()
-            } else if arrival_path.is_empty() {
-                format!("Berlin Hbf|{}", departure_path)
-            } else if departure_path.is_empty() {
-                format!("{}|Berlin Hbf", arrival_path)
-            } else {
-                format!("{}|Berlin Hbf|{}", arrival_path, departure_path)
-            };
-
-            let dest = departure_path.split('|')
-                .last()
-                .map(|s: &str| s.to_string())
-                .filter(|s| !s.is_empty())
-                .unwrap_or_else(|| "Berlin Hbf".to_string());
-
-            
-            let mut categories: Vec<String> = stop.messages.iter()
-                .chain(dp.messages.iter())
-                .filter_map(|m| m.category.clone())
-                .collect();
-            categories.sort();
-            categories.dedup();
-            let joined_disturbances = categories.join("|");
-
-            
-            flat_departures.push(Departure {
-                trip_id: stop.id.clone(),
-                train: train_name,
-                destination: dest,
-                path: full_route, 
-                scheduled_time: formatted_time,
-                platform: dp.platform.clone().unwrap_or_else(|| "--".to_string()),
-                delay: delay_min,
-                disturbances: joined_disturbances,
-            });
-        }
-    }
-    Ok(flat_departures)
+/// The Arrow schema shared by the Parquet bronze files and the Flight
+/// service, so both describe the same departures data.
+pub(crate) fn departures_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("station_id", DataType::Utf8, false),
+        Field::new("trip_id", DataType::Utf8, false),
+        Field::new("train", DataType::Utf8, false),
+        Field::new("destination", DataType::Utf8, false),
+        Field::new("path", DataType::Utf8, false),
+        Field::new("scheduled_time", DataType::Utf8, false),
+        Field::new("platform", DataType::Utf8, true),
+        Field::new("delay", DataType::Int32, true),
+        Field::new("service_notices", DataType::Utf8, true),
+        Field::new("stop_sequence", DataType::Utf8, true), // JSON array of HAFAS StopVisit, "" when not enriched
+        Field::new("stop_lat", DataType::Float64, true), // from the GTFS stops.txt sidecar
+        Field::new("stop_lon", DataType::Float64, true), // from the GTFS stops.txt sidecar
+        Field::new("route_short_name", DataType::Utf8, true), // from the GTFS routes.txt sidecar
+    ]))
 }
 
-
-
-fn write_parquet(departures: &Vec<Departure>) -> Result<(), Box<dyn std::error::Error>> {
-    // 1. Create the data arrays
+pub(crate) fn departures_to_batch(
+    departures: &[Departure],
+) -> Result<RecordBatch, Box<dyn std::error::Error>> {
+    let station_id_array = Arc::new(StringArray::from(departures.iter().map(|d| d.station_id.as_str()).collect::<Vec<&str>>()));
     let trip_id_array = Arc::new(StringArray::from(departures.iter().map(|d| d.trip_id.as_str()).collect::<Vec<&str>>()));
     let train_array = Arc::new(StringArray::from(departures.iter().map(|d| d.train.as_str()).collect::<Vec<&str>>()));
     let dest_array = Arc::new(StringArray::from(departures.iter().map(|d| d.destination.as_str()).collect::<Vec<&str>>()));
@@ -373,27 +246,26 @@ fn write_parquet(departures: &Vec<Departure>) -> Result<(), Box<dyn std::error::
     let delay_array = Arc::new(Int32Array::from(departures.iter().map(|d| Some(d.delay)).collect::<Vec<Option<i32>>>()));
     let dist_array = Arc::new(StringArray::from(departures.iter().map(|d| d.disturbances.as_str()).collect::<Vec<&str>>()));
     let platform_array = Arc::new(StringArray::from(departures.iter().map(|d| d.platform.as_str()).collect::<Vec<&str>>()));
+    let stop_sequence_array = Arc::new(StringArray::from(departures.iter().map(|d| d.stop_sequence.as_str()).collect::<Vec<&str>>()));
+    let stop_lat_array = Arc::new(Float64Array::from(departures.iter().map(|d| d.stop_lat).collect::<Vec<Option<f64>>>()));
+    let stop_lon_array = Arc::new(Float64Array::from(departures.iter().map(|d| d.stop_lon).collect::<Vec<Option<f64>>>()));
+    let route_short_name_array = Arc::new(StringArray::from(departures.iter().map(|d| d.route_short_name.as_str()).collect::<Vec<&str>>()));
 
-    // 2. Define the Schema 
-    let schema = Arc::new(Schema::new(vec![
-        Field::new("trip_id", DataType::Utf8, false),
-        Field::new("train", DataType::Utf8, false),
-        Field::new("destination", DataType::Utf8, false),
-        Field::new("path", DataType::Utf8, false),
-        Field::new("scheduled_time", DataType::Utf8, false),
-        Field::new("platform", DataType::Utf8, true),
-        Field::new("delay", DataType::Int32, true),
-        Field::new("service_notices", DataType::Utf8, true),
-    ]));
-
-    // 3. Create the Batch
+    let schema = departures_schema();
     let batch = RecordBatch::try_new(
-        schema.clone(), 
+        schema,
         vec![
-            trip_id_array, train_array, dest_array, path_array, 
-            time_array, platform_array, delay_array, dist_array
+            station_id_array, trip_id_array, train_array, dest_array, path_array,
+            time_array, platform_array, delay_array, dist_array, stop_sequence_array,
+            stop_lat_array, stop_lon_array, route_short_name_array
         ]
     )?;
+    Ok(batch)
+}
+
+fn write_parquet(departures: &Vec<Departure>) -> Result<(), Box<dyn std::error::Error>> {
+    let schema = departures_schema();
+    let batch = departures_to_batch(departures)?;
 
     // 4. Write to file
     // let path = format!("../data/bronze/bronze_{}.parquet", Utc::now().timestamp());
@@ -409,7 +281,7 @@ fn write_parquet(departures: &Vec<Departure>) -> Result<(), Box<dyn std::error::
     Ok(())
 }
 
-fn calculate_delay_minutes(planned: &str, actual: &str) -> i32 {
+pub(crate) fn calculate_delay_minutes(planned: &str, actual: &str) -> i32 {
     let format = "%y%m%d%H%M";
     let p_time = NaiveDateTime::parse_from_str(planned, format);
     let a_time = NaiveDateTime::parse_from_str(actual, format);
@@ -420,7 +292,7 @@ fn calculate_delay_minutes(planned: &str, actual: &str) -> i32 {
     }
 }
 
-fn format_db_time(raw: &str) -> String {
+pub(crate) fn format_db_time(raw: &str) -> String {
     if raw.len() != 10 { return raw.to_string(); }
     // Input: 2602101730 (YYMMDDHHMM)
     match NaiveDateTime::parse_from_str(raw, "%y%m%d%H%M") {
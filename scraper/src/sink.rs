@@ -0,0 +1,102 @@
+use duckdb::Connection;
+use std::error::Error;
+
+use crate::Departure;
+
+/// Ensure `silver_departures` (current state, keyed on `(trip_id,
+/// scheduled_time)`) and `departure_observations` (append-only history)
+/// both exist.
+pub fn ensure_schema(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS silver_departures (
+            station_id VARCHAR NOT NULL,
+            trip_id VARCHAR NOT NULL,
+            scheduled_time VARCHAR NOT NULL,
+            train VARCHAR,
+            destination VARCHAR,
+            path VARCHAR,
+            platform VARCHAR,
+            delay INTEGER,
+            disturbances VARCHAR,
+            stop_sequence VARCHAR,
+            stop_lat DOUBLE,
+            stop_lon DOUBLE,
+            route_short_name VARCHAR,
+            first_seen TIMESTAMP NOT NULL DEFAULT now(),
+            last_seen TIMESTAMP NOT NULL DEFAULT now(),
+            observation_count INTEGER NOT NULL DEFAULT 1,
+            PRIMARY KEY (trip_id, scheduled_time)
+        );
+        CREATE TABLE IF NOT EXISTS departure_observations (
+            trip_id VARCHAR NOT NULL,
+            scheduled_time VARCHAR NOT NULL,
+            observed_at TIMESTAMP NOT NULL DEFAULT now(),
+            delay INTEGER,
+            platform VARCHAR,
+            disturbances VARCHAR
+        )",
+    )?;
+    Ok(())
+}
+
+/// Upsert each departure into `silver_departures`, keyed on
+/// `(trip_id, scheduled_time)`, so the same departure observed across
+/// consecutive fetches doesn't get duplicated the way a fresh
+/// `bronze_<timestamp>.parquet` per run would — and append one row per
+/// departure to `departure_observations` so the delay/platform/disturbances
+/// *at each fetch* stay reconstructible. `silver_departures` only keeps the
+/// latest values plus a running `observation_count`; it is not itself a
+/// history, which is why the append-only log exists alongside it.
+pub fn upsert_departures(conn: &Connection, departures: &[Departure]) -> Result<(), Box<dyn Error>> {
+    ensure_schema(conn)?;
+
+    for departure in departures {
+        conn.execute(
+            "INSERT INTO silver_departures (
+                station_id, trip_id, scheduled_time, train, destination, path, platform,
+                delay, disturbances, stop_sequence, stop_lat, stop_lon, route_short_name
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (trip_id, scheduled_time) DO UPDATE SET
+                delay = excluded.delay,
+                platform = excluded.platform,
+                path = excluded.path,
+                disturbances = excluded.disturbances,
+                stop_sequence = excluded.stop_sequence,
+                stop_lat = excluded.stop_lat,
+                stop_lon = excluded.stop_lon,
+                route_short_name = excluded.route_short_name,
+                last_seen = now(),
+                observation_count = silver_departures.observation_count + 1",
+            duckdb::params![
+                departure.station_id,
+                departure.trip_id,
+                departure.scheduled_time,
+                departure.train,
+                departure.destination,
+                departure.path,
+                departure.platform,
+                departure.delay,
+                departure.disturbances,
+                departure.stop_sequence,
+                departure.stop_lat,
+                departure.stop_lon,
+                departure.route_short_name,
+            ],
+        )?;
+
+        conn.execute(
+            "INSERT INTO departure_observations (
+                trip_id, scheduled_time, delay, platform, disturbances
+            ) VALUES (?, ?, ?, ?, ?)",
+            duckdb::params![
+                departure.trip_id,
+                departure.scheduled_time,
+                departure.delay,
+                departure.platform,
+                departure.disturbances,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use async_trait::async_trait;
+use quick_xml::de::from_str;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::{calculate_delay_minutes, format_db_time, Departure};
+
+use super::TimetableSource;
+
+#[derive(Debug, Deserialize, Clone)]
+struct Timetable {
+    #[serde(rename = "s", default)]
+    stops: Vec<Stop>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Stop {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "m", default)]
+    messages: Vec<Message>,
+    #[serde(rename = "ar")]
+    arrivals: Option<TrainEvent>,
+    #[serde(rename = "dp")]
+    departures: Option<TrainEvent>,
+    #[serde(rename = "tl")]
+    train_line: Option<TrainLine>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Message {
+    #[serde(rename = "@t")]
+    msg_type: Option<String>,
+    #[serde(rename = "@cat")]
+    category: Option<String>,
+    #[serde(rename = "@from")]
+    valid_from: Option<String>,
+    #[serde(rename = "@to")]
+    valid_to: Option<String>,
+    #[serde(rename = "@ts")]
+    timestamp: Option<String>,
+    #[serde(rename = "@ts-tts")]
+    ts_tts: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct TrainEvent {
+    #[serde(rename = "@ct")] actual_time: Option<String>,
+    #[serde(rename = "@pt")] planned_time: Option<String>,
+    #[serde(rename = "@ppth")] planned_path: Option<String>,
+    #[serde(rename = "@cpth")] changed_path: Option<String>,
+    #[serde(rename = "@pp")] platform: Option<String>,
+    #[serde(rename = "@l")] line: Option<String>,
+    #[serde(rename = "@c")] category: Option<String>,
+    #[serde(rename = "@n")] number: Option<String>,
+    #[serde(rename = "m", default)] messages: Vec<Message>,
+}
+
+impl TrainEvent {
+    fn path(&self) -> Option<String> {
+        self.changed_path.clone().or(self.planned_path.clone())
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct TrainLine {
+    #[serde(rename = "@c")] // Category (ICE)
+    category: Option<String>,
+    #[serde(rename = "@n")] // Number (147)
+    number: Option<String>,
+}
+
+/// `TimetableSource` backed by the Deutsche Bahn Timetables XML API
+/// (`apis.deutschebahn.com/db-api-marketplace/apis/timetables`).
+pub struct DbTimetables {
+    client: Client,
+    client_id: String,
+    api_key: String,
+}
+
+impl DbTimetables {
+    pub fn new(client_id: String, api_key: String) -> Self {
+        Self {
+            client: Client::new(),
+            client_id,
+            api_key,
+        }
+    }
+}
+
+#[async_trait]
+impl TimetableSource for DbTimetables {
+    async fn fetch_plan(
+        &self,
+        station: &str,
+        date: &str,
+        hour: &str,
+    ) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let mut map = HashMap::new();
+
+        let url = format!(
+            "https://apis.deutschebahn.com/db-api-marketplace/apis/timetables/v1/plan/{}/{}/{}?sub=yes",
+            station, date, hour
+        );
+
+        let resp = self
+            .client
+            .get(&url)
+            .header("DB-Client-Id", &self.client_id)
+            .header("DB-Api-Key", &self.api_key)
+            .header("Accept", "application/xml")
+            .send()
+            .await?;
+
+        if resp.status().is_success() {
+            let text = resp.text().await?;
+            let timetable: Timetable = from_str(&text)?;
+
+            for stop in timetable.stops {
+                let mut train_name = None;
+
+                let event = stop.departures.as_ref().or(stop.arrivals.as_ref());
+                if let Some(ev) = event {
+                    if let Some(l) = &ev.line {
+                        if !l.is_empty() {
+                            train_name = Some(l.clone());
+                        }
+                    }
+                }
+
+                if train_name.is_none() {
+                    if let Some(tl) = &stop.train_line {
+                        match (&tl.category, &tl.number) {
+                            (Some(c), Some(n)) => train_name = Some(format!("{} {}", c, n)),
+                            (Some(c), None) => train_name = Some(c.clone()),
+                            _ => {}
+                        }
+                    }
+                }
+
+                let final_name = train_name.unwrap_or_else(|| {
+                    format!("ID:{}", &stop.id.chars().take(8).collect::<String>())
+                });
+
+                map.insert(stop.id, final_name);
+            }
+        } else {
+            eprintln!(" Could not fetch hour {}: Status {}", hour, resp.status());
+        }
+
+        Ok(map)
+    }
+
+    async fn fetch_changes(
+        &self,
+        station: &str,
+        plan_map: &HashMap<String, String>,
+    ) -> Result<Vec<Departure>, Box<dyn Error>> {
+        let url = format!(
+            "https://apis.deutschebahn.com/db-api-marketplace/apis/timetables/v1/fchg/{}?sub=yes",
+            station
+        );
+
+        let resp = self
+            .client
+            .get(&url)
+            .header("DB-Client-Id", &self.client_id)
+            .header("DB-Api-Key", &self.api_key)
+            .header("Accept", "application/xml")
+            .send()
+            .await?;
+
+        let text = resp.text().await?;
+        let timetable: Timetable = from_str(&text)?;
+        let mut flat_departures = vec![];
+
+        for stop in timetable.stops {
+            if let Some(dp) = &stop.departures {
+                let raw_time = dp
+                    .planned_time
+                    .clone()
+                    .unwrap_or_else(|| dp.actual_time.clone().unwrap_or_default());
+                let formatted_time = format_db_time(&raw_time);
+
+                let delay_min = if let (Some(p), Some(a)) = (&dp.planned_time, &dp.actual_time) {
+                    calculate_delay_minutes(p, a)
+                } else {
+                    0
+                };
+
+                let train_name = {
+                    let change_name = match (&dp.line, &dp.category, &dp.number) {
+                        (Some(l), _, _) if !l.is_empty() => Some(l.clone()),
+                        (_, Some(c), Some(n)) => Some(format!("{} {}", c, n)),
+                        _ => None,
+                    };
+
+                    change_name
+                        .or_else(|| plan_map.get(&stop.id).cloned())
+                        .or_else(|| {
+                            stop.train_line.as_ref().and_then(|tl| {
+                                match (&tl.category, &tl.number) {
+                                    (Some(c), Some(n)) => Some(format!("{} {}", c, n)),
+                                    (Some(c), None) => Some(c.clone()),
+                                    _ => None,
+                                }
+                            })
+                        })
+                        .unwrap_or_else(|| "Unknown".to_string())
+                };
+
+                let arrival_path = stop
+                    .arrivals
+                    .as_ref()
+                    .and_then(|ar| ar.path())
+                    .unwrap_or_default();
+
+                let departure_path = dp.path().unwrap_or_default();
+
+                let full_route = if arrival_path.is_empty() && departure_path.is_empty() {
+                    station.to_string()
+                } else if arrival_path.is_empty() {
+                    format!("{}|{}", station, departure_path)
+                } else if departure_path.is_empty() {
+                    format!("{}|{}", arrival_path, station)
+                } else {
+                    format!("{}|{}|{}", arrival_path, station, departure_path)
+                };
+
+                let dest = departure_path
+                    .split('|')
+                    .last()
+                    .map(|s: &str| s.to_string())
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or_else(|| station.to_string());
+
+                let mut categories: Vec<String> = stop
+                    .messages
+                    .iter()
+                    .chain(dp.messages.iter())
+                    .filter_map(|m| m.category.clone())
+                    .collect();
+                categories.sort();
+                categories.dedup();
+                let joined_disturbances = categories.join("|");
+
+                flat_departures.push(Departure {
+                    station_id: station.to_string(),
+                    trip_id: stop.id.clone(),
+                    train: train_name,
+                    destination: dest,
+                    path: full_route,
+                    scheduled_time: formatted_time,
+                    platform: dp.platform.clone().unwrap_or_else(|| "--".to_string()),
+                    delay: delay_min,
+                    disturbances: joined_disturbances,
+                    stop_sequence: String::new(),
+                    stop_lat: None,
+                    stop_lon: None,
+                    route_short_name: String::new(),
+                });
+            }
+        }
+        Ok(flat_departures)
+    }
+}
@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::Departure;
+
+mod db;
+
+pub use db::DbTimetables;
+
+/// A provider of station timetable data. Each backend (Deutsche Bahn
+/// Timetables, HAFAS, an onboard API, ...) owns its own wire formats and
+/// normalizes everything down to the crate's `Departure` type.
+#[async_trait]
+pub trait TimetableSource {
+    /// Fetch the baseline plan for a single `date`/`hour` slot at `station`,
+    /// keyed by stop id. Callers typically call this once per hour in a
+    /// rolling window to build up a lookup map used to backfill train names.
+    async fn fetch_plan(
+        &self,
+        station: &str,
+        date: &str,
+        hour: &str,
+    ) -> Result<HashMap<String, String>, Box<dyn Error>>;
+
+    /// Fetch the current changes/departures for `station`, using `plan_map`
+    /// to resolve train names that the changes feed alone doesn't carry.
+    async fn fetch_changes(
+        &self,
+        station: &str,
+        plan_map: &HashMap<String, String>,
+    ) -> Result<Vec<Departure>, Box<dyn Error>>;
+}
+
+/// Select a `TimetableSource` implementation by name, e.g. from the
+/// `TIMETABLE_SOURCE` env var or a CLI flag. Unknown names fall back to the
+/// Deutsche Bahn source so existing deployments keep working untouched.
+pub fn choose_source(name: &str, client_id: String, api_key: String) -> Box<dyn TimetableSource> {
+    match name {
+        "db" | "deutsche-bahn" => Box::new(DbTimetables::new(client_id, api_key)),
+        // Room for "hafas" and "onboard" backends once those land.
+        other => {
+            eprintln!(
+                "Unknown timetable source '{}', falling back to Deutsche Bahn Timetables",
+                other
+            );
+            Box::new(DbTimetables::new(client_id, api_key))
+        }
+    }
+}